@@ -0,0 +1,205 @@
+//! Asymmetric (PASETO) registry authentication, modeled on cargo's asymmetric registry tokens.
+//!
+//! Instead of a long-lived password living in `config.toml`, a registry namespace can store a
+//! PASETO v3 secret key (a PASERK `k3.secret` string). Every request mints a fresh, short-lived
+//! `v3.public` token scoped to that request rather than reusing a cached one, so compromising a
+//! single token never grants more than the operation it was minted for.
+use std::time::SystemTime;
+
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use oci_distribution::secrets::RegistryAuth;
+use p384::SecretKey;
+use rusty_paseto::core::{Footer, Paseto, PasetoAsymmetricPrivateKey, Payload, Public, V3};
+use serde::Serialize;
+use sha2::{Digest, Sha384};
+
+/// The operation a minted token authorizes. Embedding this in the claims lets a registry reject a
+/// pull-scoped token presented for a push, and vice versa.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutation {
+    Pull,
+    Push,
+}
+
+impl Mutation {
+    fn as_str(self) -> &'static str {
+        match self {
+            Mutation::Pull => "pull",
+            Mutation::Push => "push",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iat: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<&'a str>,
+    mutation: &'static str,
+    name: &'a str,
+    vers: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    challenge: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct TokenFooter<'a> {
+    url: &'a str,
+    kid: String,
+}
+
+/// Mints a fresh `v3.public` PASETO signed with `secret_key` (a PASERK `k3.secret` string),
+/// authorizing `mutation` on `name`@`vers`. The footer embeds `registry_url` and the signing key's
+/// PASERK id so the registry can both pick the matching public key and reject tokens minted for a
+/// different registry. Never cache the returned token: `iat` must stay current, and the server is
+/// expected to validate it as recent.
+pub fn mint_token(
+    secret_key: &str,
+    registry_url: &str,
+    mutation: Mutation,
+    name: &str,
+    vers: &str,
+    sub: Option<&str>,
+    challenge: Option<&str>,
+) -> anyhow::Result<String> {
+    let key_bytes = decode_paserk_secret(secret_key)?;
+    let key = PasetoAsymmetricPrivateKey::<V3, Public>::from(key_bytes.as_slice());
+
+    let iat = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+    let claims = Claims {
+        iat,
+        sub,
+        mutation: mutation.as_str(),
+        name,
+        vers,
+        challenge,
+    };
+    let payload = serde_json::to_string(&claims).context("Unable to serialize token claims")?;
+
+    let footer = TokenFooter {
+        url: registry_url,
+        kid: paserk_key_id(&key_bytes).context("Unable to derive public key id")?,
+    };
+    let footer = serde_json::to_string(&footer).context("Unable to serialize token footer")?;
+
+    Paseto::<V3, Public>::builder()
+        .set_payload(Payload::from(payload.as_str()))
+        .set_footer(Footer::from(footer.as_str()))
+        .try_sign(&key)
+        .context("Unable to sign PASETO token")
+}
+
+/// Mints a token for `mutation` on `name`@`vers` and wraps it as a [`RegistryAuth::Bearer`],
+/// ready to hand to the OCI client
+pub fn mint_bearer_auth(
+    secret_key: &str,
+    registry_url: &str,
+    mutation: Mutation,
+    name: &str,
+    vers: &str,
+) -> anyhow::Result<RegistryAuth> {
+    let token = mint_token(secret_key, registry_url, mutation, name, vers, None, None)?;
+    Ok(RegistryAuth::Bearer(token))
+}
+
+/// Decodes a PASERK `k3.secret.<base64url>` string into raw key bytes
+fn decode_paserk_secret(paserk: &str) -> anyhow::Result<Vec<u8>> {
+    let encoded = paserk
+        .strip_prefix("k3.secret.")
+        .ok_or_else(|| anyhow::anyhow!("Expected a k3.secret PASERK key"))?;
+    URL_SAFE_NO_PAD
+        .decode(encoded)
+        .context("Invalid PASERK encoding")
+}
+
+/// Computes the PASERK `k3.pid` id of the *public* key paired with `secret_key_bytes`, used as the
+/// footer's `kid`. The registry only ever holds the public key, so the id must be derived from it
+/// (not the secret key) for the registry to be able to reproduce it and select the matching key.
+///
+/// Per the PASERK key-ID algorithm, this hashes the id's own header concatenated with the full
+/// serialized PASERK string of the key (`"k3.pid." + "k3.public." + base64url(raw bytes)`), not
+/// just the key's own header plus its raw bytes.
+fn paserk_key_id(secret_key_bytes: &[u8]) -> anyhow::Result<String> {
+    let public_key_bytes = derive_public_key_bytes(secret_key_bytes)?;
+    let public_paserk = format!("k3.public.{}", URL_SAFE_NO_PAD.encode(&public_key_bytes));
+
+    let mut hasher = Sha384::new();
+    hasher.update(b"k3.pid.");
+    hasher.update(public_paserk.as_bytes());
+    let digest = hasher.finalize();
+    Ok(format!("k3.pid.{}", URL_SAFE_NO_PAD.encode(&digest[..33])))
+}
+
+/// Derives the compressed SEC1 public key bytes paired with a P-384 PASERK secret key
+fn derive_public_key_bytes(secret_key_bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let secret = SecretKey::from_slice(secret_key_bytes).context("Invalid PASERK secret key")?;
+    Ok(secret.public_key().to_encoded_point(true).as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_secret_paserk(fill: u8) -> String {
+        let raw = [fill; 48];
+        format!("k3.secret.{}", URL_SAFE_NO_PAD.encode(raw))
+    }
+
+    #[test]
+    fn paserk_key_id_is_deterministic_and_tagged_pid() {
+        let key_bytes = decode_paserk_secret(&sample_secret_paserk(0x11)).unwrap();
+
+        let id_a = paserk_key_id(&key_bytes).unwrap();
+        let id_b = paserk_key_id(&key_bytes).unwrap();
+
+        assert_eq!(id_a, id_b);
+        assert!(id_a.starts_with("k3.pid."));
+    }
+
+    #[test]
+    fn paserk_key_id_differs_for_different_keys() {
+        let key_a = decode_paserk_secret(&sample_secret_paserk(0x11)).unwrap();
+        let key_b = decode_paserk_secret(&sample_secret_paserk(0x22)).unwrap();
+
+        assert_ne!(
+            paserk_key_id(&key_a).unwrap(),
+            paserk_key_id(&key_b).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_paserk_secret_rejects_wrong_prefix() {
+        assert!(decode_paserk_secret("k3.public.AAAA").is_err());
+    }
+
+    #[test]
+    fn mint_token_embeds_the_derived_key_id_in_the_footer() {
+        let secret = sample_secret_paserk(0x11);
+        let token = mint_token(
+            &secret,
+            "https://example.com",
+            Mutation::Pull,
+            "wasi:http",
+            "0.2.0",
+            None,
+            None,
+        )
+        .expect("minting should succeed");
+
+        let key_bytes = decode_paserk_secret(&secret).unwrap();
+        let expected_kid = paserk_key_id(&key_bytes).unwrap();
+
+        let footer_b64 = token
+            .rsplit('.')
+            .next()
+            .expect("token should have a footer segment");
+        let footer_json = URL_SAFE_NO_PAD
+            .decode(footer_b64)
+            .expect("footer should decode");
+        let footer: serde_json::Value =
+            serde_json::from_slice(&footer_json).expect("footer should be json");
+
+        assert_eq!(footer["kid"], expected_kid);
+    }
+}
@@ -1,6 +1,8 @@
 //! Definitions and helpers for loading the dependency configuration file
 use std::collections::BTreeMap;
+use std::path::Path;
 
+use anyhow::Context;
 use oci_distribution::{
     client::{ClientConfig, ClientProtocol},
     secrets::RegistryAuth,
@@ -8,6 +10,9 @@ use oci_distribution::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::auth::{self, Mutation};
+use crate::credentials::Credentials;
+
 /// The default config file name
 pub const DEFAULT_CONFIG_FILE_NAME: &str = "config.toml";
 /// The default registry for pulling dependencies
@@ -50,13 +55,44 @@ impl Default for Config {
     }
 }
 
+impl Config {
+    /// Loads a config from the given path, falling back to the default config if the file does
+    /// not exist
+    pub async fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(raw) => toml::from_str(&raw).context("Unable to parse config.toml"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).context("Unable to read config.toml"),
+        }
+    }
+
+    /// Serializes and writes the config to the given path, rejecting an invalid `protocol` on any
+    /// registry config instead of silently falling back to https
+    pub async fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        self.default_config
+            .validate_protocol()
+            .context("Invalid default registry config")?;
+        for (namespace, registry_config) in &self.namespaces {
+            registry_config
+                .validate_protocol()
+                .with_context(|| format!("Invalid config for namespace {namespace}"))?;
+        }
+
+        let raw = toml::to_string_pretty(self).context("Unable to serialize config.toml")?;
+        tokio::fs::write(path, raw)
+            .await
+            .context("Unable to write config.toml")
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RegistryConfig {
     /// The registry to use for pulling dependencies
     pub registry: String,
     /// The protocol to use for pulling dependencies. This defaults to "https" and only accepts the
-    /// strings "https" and "http". Any invalid strings will default to https instead
+    /// strings "https" and "http". Hand-edited files with anything else fall back to https when
+    /// read; [`Config::write`] (used by the `config` subcommand) rejects invalid values outright
     pub protocol: Option<String>,
     /// The registry subpath to use for pulling dependencies. This is the path before the actual
     /// artifact (e.g. if your reference is ghcr.io/my/subpath/component:0.1.0, then the subpath
@@ -65,6 +101,10 @@ pub struct RegistryConfig {
     pub registry_subpath: Option<String>,
     /// Optional authentication details to use for the registry
     pub auth: Option<Auth>,
+    /// Retry/backoff settings to use for this registry. Defaults to [`RetryConfig::default`] if
+    /// not set
+    #[serde(default)]
+    pub retry: Option<RetryConfig>,
 }
 
 impl Default for RegistryConfig {
@@ -74,39 +114,129 @@ impl Default for RegistryConfig {
             registry_subpath: Some(DEFAULT_REGISTRY_SUBPATH.to_string()),
             protocol: None,
             auth: None,
+            retry: None,
         }
     }
 }
 
 impl RegistryConfig {
-    /// Returns an OCI client and auth for the registry
-    pub fn get_client(&self) -> (Client, RegistryAuth) {
+    /// Returns the configured retry policy, falling back to [`RetryConfig::default`]
+    pub fn retry_policy(&self) -> crate::retry::RetryPolicy {
+        self.retry.unwrap_or_default().into()
+    }
+
+    /// Validates that `protocol`, if set, is exactly `"http"` or `"https"`
+    pub fn validate_protocol(&self) -> anyhow::Result<()> {
+        match self.protocol.as_deref() {
+            None | Some("http") | Some("https") => Ok(()),
+            Some(other) => anyhow::bail!(
+                "Invalid protocol {other:?} for registry {}: must be \"http\" or \"https\"",
+                self.registry
+            ),
+        }
+    }
+
+    /// Returns an OCI client and the auth to use for `mutation` (pull or push) against `name`@
+    /// `vers`. When the namespace is configured for asymmetric auth, this mints a brand new
+    /// PASETO for the request every time it's called; never cache the returned `RegistryAuth`.
+    ///
+    /// Credential resolution follows: an explicit `auth` on this config, then a matching entry in
+    /// `credentials` (the persisted `login` store), then anonymous.
+    pub fn get_client(
+        &self,
+        mutation: Mutation,
+        name: &str,
+        vers: &str,
+        credentials: Option<&Credentials>,
+    ) -> anyhow::Result<(Client, RegistryAuth)> {
+        let protocol = match self.protocol.as_deref() {
+            Some("http") => ClientProtocol::Http,
+            Some("https") => ClientProtocol::Https,
+            Some(_) => {
+                // TODO log warning
+                ClientProtocol::Https
+            }
+            None => ClientProtocol::Https,
+        };
         let client = Client::new(ClientConfig {
-            protocol: match self.protocol.as_deref() {
-                Some("http") => ClientProtocol::Http,
-                Some("https") => ClientProtocol::Https,
-                Some(_) => {
-                    // TODO log warning
-                    ClientProtocol::Https
-                }
-                None => ClientProtocol::Https,
-            },
+            protocol,
             ..Default::default()
         });
-        let auth = self
-            .auth
-            .clone()
-            .map(|auth| RegistryAuth::Basic(auth.username, auth.password))
-            .unwrap_or(RegistryAuth::Anonymous);
-        (client, auth)
+
+        let auth = match &self.auth {
+            Some(Auth::Basic { username, password }) => {
+                RegistryAuth::Basic(username.clone(), password.clone())
+            }
+            Some(Auth::Asymmetric { secret_key }) => {
+                let scheme = self.protocol.as_deref().unwrap_or("https");
+                let url = format!("{scheme}://{}", self.registry);
+                auth::mint_bearer_auth(secret_key, &url, mutation, name, vers)?
+            }
+            None => credentials
+                .and_then(|credentials| credentials.get(&self.registry))
+                .map(|stored| RegistryAuth::Basic(stored.username.clone(), stored.password.clone()))
+                .unwrap_or(RegistryAuth::Anonymous),
+        };
+        Ok((client, auth))
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Clone)]
+/// Authentication details for a registry namespace: either a long-lived HTTP Basic
+/// username/password, or a PASETO asymmetric secret key used to mint short-lived tokens per
+/// request. See [`crate::auth`] for how the asymmetric side is signed.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase", untagged)]
+pub enum Auth {
+    /// HTTP Basic authentication
+    Basic {
+        /// The username to use for authentication
+        username: String,
+        /// The password to use for authentication
+        password: String,
+    },
+    /// Asymmetric (PASETO) token authentication. `secret_key` is a PASERK `k3.secret` string
+    Asymmetric {
+        /// The PASERK `k3.secret` key used to sign minted tokens
+        secret_key: String,
+    },
+}
+
+/// Retry/backoff settings for a registry. Only idempotent operations (pulls, manifest/blob GETs)
+/// should use the full retry budget; pushes only retry connection-level failures regardless of
+/// `max_attempts` (see [`crate::retry::RetryClass`]).
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(rename_all = "camelCase")]
-pub struct Auth {
-    /// The username to use for authentication
-    pub username: String,
-    /// The password to use for authentication
-    pub password: String,
+pub struct RetryConfig {
+    /// The total number of attempts to make, including the first
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// The base delay (in milliseconds) used to compute each attempt's exponential backoff
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub base_delay_ms: u64,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay_ms() -> u64 {
+    200
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            base_delay_ms: default_retry_base_delay_ms(),
+        }
+    }
+}
+
+impl From<RetryConfig> for crate::retry::RetryPolicy {
+    fn from(config: RetryConfig) -> Self {
+        crate::retry::RetryPolicy {
+            max_attempts: config.max_attempts,
+            base_delay: std::time::Duration::from_millis(config.base_delay_ms),
+        }
+    }
 }
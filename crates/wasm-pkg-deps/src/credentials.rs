@@ -0,0 +1,82 @@
+//! Definitions and helpers for loading the `credentials.toml` file. Unlike `config.toml`, this
+//! file is written to by `wasm-pkg login`/`logout` rather than hand-edited, so HTTP Basic
+//! credentials can persist between invocations instead of only coming from `-u/-p` flags or
+//! `WASM_PKG_USERNAME`/`WASM_PKG_PASSWORD` env vars.
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+/// The default credentials file name
+pub const DEFAULT_CREDENTIALS_FILE_NAME: &str = "credentials.toml";
+
+/// A store of HTTP Basic credentials keyed by registry host (e.g. `ghcr.io`)
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Credentials {
+    /// The stored credentials, keyed by registry host
+    #[serde(default)]
+    pub registries: BTreeMap<String, StoredCredential>,
+}
+
+impl Credentials {
+    /// Loads the credentials store from the given path, returning an empty store if the file
+    /// does not exist
+    pub async fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(raw) => toml::from_str(&raw).context("Unable to parse credentials.toml"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).context("Unable to read credentials.toml"),
+        }
+    }
+
+    /// Serializes and writes the credentials store to the given path. On Unix the file is created
+    /// with owner-only read/write permissions from the start (not narrowed after the fact), so the
+    /// plaintext passwords are never briefly world-readable under the process umask.
+    pub async fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let raw = toml::to_string_pretty(self).context("Unable to serialize credentials.toml")?;
+
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options
+            .open(path.as_ref())
+            .await
+            .context("Unable to create credentials.toml")?;
+        file.write_all(raw.as_bytes())
+            .await
+            .context("Unable to write credentials.toml")
+    }
+
+    /// Returns the stored credential for `registry`, if any
+    pub fn get(&self, registry: &str) -> Option<&StoredCredential> {
+        self.registries.get(registry)
+    }
+
+    /// Stores (or replaces) the credential for `registry`
+    pub fn set(&mut self, registry: impl Into<String>, credential: StoredCredential) {
+        self.registries.insert(registry.into(), credential);
+    }
+
+    /// Removes the stored credential for `registry`, returning it if one was present
+    pub fn remove(&mut self, registry: &str) -> Option<StoredCredential> {
+        self.registries.remove(registry)
+    }
+}
+
+/// A single stored HTTP Basic credential
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredCredential {
+    /// The username to authenticate with
+    pub username: String,
+    /// The password to authenticate with
+    pub password: String,
+}
@@ -1,17 +1,32 @@
 //! A library for pulling wit dependencies from a registry
-use oci_distribution::secrets::RegistryAuth;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::Path;
+
+use anyhow::Context;
+use oci_distribution::Reference;
 use oci_wasm::WasmClient;
+use sha2::{Digest, Sha256};
 
+pub mod auth;
 pub mod config;
+pub mod credentials;
 pub mod manifest;
+pub mod retry;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 
 pub use config::Config;
+pub use credentials::Credentials;
+
+use auth::Mutation;
+use config::RegistryConfig;
+use manifest::{DependencyConfig, LockedPackage, WitLock, WitManifest};
+use retry::RetryClass;
 
 /// A client for pulling dependencies specified in a manifest
 pub struct DepsClient {
-    default_client: WasmClient,
-    default_auth: RegistryAuth,
     config: Config,
+    credentials: Option<Credentials>,
 }
 
 impl Default for DepsClient {
@@ -23,11 +38,343 @@ impl Default for DepsClient {
 impl DepsClient {
     /// Create a new `DepsClient` from the given config
     pub fn new(config: Config) -> Self {
-        let (default_client, default_auth) = config.default_config.get_client();
         Self {
-            default_client: WasmClient::new(default_client),
-            default_auth,
             config,
+            credentials: None,
+        }
+    }
+
+    /// Layers a persisted credentials store (see the `login`/`logout` subcommands) in as a
+    /// fallback for namespaces whose `config.toml` entry has no explicit `auth` set
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Resolves `manifest`, pulling it and every transitive dependency discovered from the pulled
+    /// components' own WIT imports into `cache_dir`, and records the resolved set in `lock`.
+    /// Packages whose digest already matches `lock` and whose cached bytes are still present are
+    /// not re-downloaded.
+    ///
+    /// Returns an error if two requesters (manifest entries or transitive imports) require
+    /// different versions of the same package.
+    pub async fn update(
+        &self,
+        manifest: &WitManifest,
+        cache_dir: impl AsRef<Path>,
+        lock: &mut WitLock,
+    ) -> anyhow::Result<()> {
+        let cache_dir = cache_dir.as_ref();
+        tokio::fs::create_dir_all(cache_dir)
+            .await
+            .context("Unable to create cache dir")?;
+
+        // Every (requester, required version) pair seen for a given package name, so conflicting
+        // requirements can be reported together once resolution is done.
+        let mut requirements: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+        let mut queue: VecDeque<(String, DependencyConfig)> = VecDeque::new();
+        let mut resolved: BTreeMap<String, LockedPackage> = BTreeMap::new();
+        let mut seen: BTreeSet<String> = BTreeSet::new();
+
+        for (name, entry) in manifest {
+            let dep_config = entry.clone().into_config();
+            requirements
+                .entry(name.clone())
+                .or_default()
+                .push(("wit.toml".to_string(), dep_config.version.clone()));
+            queue.push_back((name.clone(), dep_config));
+        }
+
+        while let Some((name, dep_config)) = queue.pop_front() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+
+            let registry_config = self.registry_config_for(&name, &dep_config);
+            let reference = build_reference(&name, &dep_config, &registry_config)?;
+            let dest = cache_dir.join(cache_file_name(&name));
+
+            let pulled = self
+                .pull_if_changed(&reference, &registry_config, lock, &name, &dest)
+                .await?;
+            let (digest, manifest_digest) = match pulled {
+                Some((bytes, manifest_digest)) => {
+                    tokio::fs::write(&dest, &bytes)
+                        .await
+                        .context("Unable to write pulled component")?;
+                    (digest_of(&bytes), manifest_digest)
+                }
+                None => {
+                    let locked = &lock.packages[&name];
+                    (locked.digest.clone(), locked.manifest_digest.clone())
+                }
+            };
+
+            resolved.insert(
+                name.clone(),
+                LockedPackage {
+                    version: dep_config.version.clone(),
+                    registry: registry_config.registry.clone(),
+                    reference: reference.to_string(),
+                    digest,
+                    manifest_digest,
+                },
+            );
+
+            for (import_name, import_version) in transitive_imports(&dest).await? {
+                requirements
+                    .entry(import_name.clone())
+                    .or_default()
+                    .push((name.clone(), import_version.clone()));
+                if !seen.contains(&import_name) {
+                    queue.push_back((
+                        import_name,
+                        DependencyConfig {
+                            version: import_version,
+                            registry: None,
+                            protocol: None,
+                            registry_subpath: None,
+                            package_name: None,
+                        },
+                    ));
+                }
+            }
+        }
+
+        check_conflicts(&requirements)?;
+
+        lock.packages = resolved;
+        Ok(())
+    }
+
+    /// Resolves the [`RegistryConfig`] to use for `name`, preferring an explicit registry set on
+    /// the manifest entry itself, then the namespace mapping in [`Config`], then the configured
+    /// default
+    fn registry_config_for(&self, name: &str, dep_config: &DependencyConfig) -> RegistryConfig {
+        if let Some(registry) = &dep_config.registry {
+            return RegistryConfig {
+                registry: registry.clone(),
+                protocol: dep_config.protocol.clone(),
+                registry_subpath: dep_config.registry_subpath.clone(),
+                auth: None,
+                retry: None,
+            };
+        }
+
+        let namespace = name.split(':').next().unwrap_or(name);
+        self.config
+            .namespaces
+            .get(namespace)
+            .cloned()
+            .unwrap_or_else(|| self.config.default_config.clone())
+    }
+
+    /// Pulls `reference` unless `lock` already records a manifest digest for `name` that matches
+    /// the registry's *current* manifest digest (fetched fresh, not assumed from the lockfile) and
+    /// `dest` still exists on disk, in which case `None` is returned and the existing cache entry
+    /// is reused. Otherwise returns the pulled component's bytes alongside the manifest digest
+    /// they were pulled under, so the caller can record it in the lockfile for the next run.
+    ///
+    /// The manifest digest (of the OCI manifest document) and the component digest
+    /// ([`LockedPackage::digest`], of the pulled layer bytes) are different digests over different
+    /// content — never compare one against the other.
+    async fn pull_if_changed(
+        &self,
+        reference: &Reference,
+        registry_config: &RegistryConfig,
+        lock: &WitLock,
+        name: &str,
+        dest: &Path,
+    ) -> anyhow::Result<Option<(Vec<u8>, String)>> {
+        let (probe_client, probe_auth) = registry_config.get_client(
+            Mutation::Pull,
+            name,
+            reference_tag(reference),
+            self.credentials.as_ref(),
+        )?;
+        let policy = registry_config.retry_policy();
+        let current_manifest_digest = retry::retry(policy, RetryClass::Idempotent, || async {
+            probe_client
+                .fetch_manifest_digest(reference, &probe_auth)
+                .await
+                .with_context(|| format!("Unable to fetch manifest digest for {reference}"))
+        })
+        .await?;
+
+        if let Some(locked) = lock.packages.get(name) {
+            if locked.reference == reference.to_string()
+                && dest.exists()
+                && digests_match(&current_manifest_digest, &locked.manifest_digest)
+            {
+                return Ok(None);
+            }
+        }
+
+        let (client, auth) = registry_config.get_client(
+            Mutation::Pull,
+            name,
+            reference_tag(reference),
+            self.credentials.as_ref(),
+        )?;
+        let wasm_client = WasmClient::new(client);
+
+        let data = retry::retry(policy, RetryClass::Idempotent, || async {
+            wasm_client
+                .pull(reference, &auth)
+                .await
+                .with_context(|| format!("Unable to pull {reference}"))
+        })
+        .await?;
+        let layer = data
+            .layers
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No layers found for {reference}"))?;
+        Ok(Some((layer.data, current_manifest_digest)))
+    }
+}
+
+/// Builds the full OCI reference for a manifest entry: `registry/[subpath/]package-name:version`
+fn build_reference(
+    name: &str,
+    dep_config: &DependencyConfig,
+    registry_config: &RegistryConfig,
+) -> anyhow::Result<Reference> {
+    let package_name = dep_config
+        .package_name
+        .clone()
+        .unwrap_or_else(|| name.replace(':', "-"));
+    let repository = match registry_config.registry_subpath.as_deref() {
+        Some(subpath) => format!("{subpath}/{package_name}"),
+        None => package_name,
+    };
+    format!(
+        "{}/{repository}:{}",
+        registry_config.registry, dep_config.version
+    )
+    .parse()
+    .with_context(|| format!("Unable to construct OCI reference for {name}"))
+}
+
+/// The file name used to cache a pulled package inside the cache dir
+fn cache_file_name(name: &str) -> String {
+    format!("{}.wasm", name.replace([':', '/'], "-"))
+}
+
+/// The tag portion of an OCI reference, used as the `vers` claim when minting an asymmetric auth
+/// token
+fn reference_tag(reference: &Reference) -> &str {
+    reference.tag().unwrap_or_default()
+}
+
+fn digest_of(data: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(data))
+}
+
+/// Compares two OCI digests, tolerating a missing `sha256:` prefix on either side
+fn digests_match(a: &str, b: &str) -> bool {
+    a.trim_start_matches("sha256:") == b.trim_start_matches("sha256:")
+}
+
+/// Decodes the component at `path` and returns the namespace:name and version of every package it
+/// imports, so the resolver can recurse into its transitive dependencies
+async fn transitive_imports(path: &Path) -> anyhow::Result<Vec<(String, String)>> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .context("Unable to read pulled component")?;
+    let decoded = wit_component::decode(&bytes).context("Unable to decode component WIT")?;
+    let resolve = decoded.resolve();
+
+    let mut imports = Vec::new();
+    for (_, world) in resolve.worlds.iter() {
+        for item in world.imports.values() {
+            let wit_parser::WorldItem::Interface(id) = item else {
+                continue;
+            };
+            let Some(package_id) = resolve.interfaces[*id].package else {
+                continue;
+            };
+            let package = &resolve.packages[package_id];
+            let Some(version) = &package.name.version else {
+                continue;
+            };
+            imports.push((
+                format!("{}:{}", package.name.namespace, package.name.name),
+                version.to_string(),
+            ));
         }
     }
+    Ok(imports)
+}
+
+/// Checks that every package with more than one requester was asked for the same version by all
+/// of them, returning an error naming every conflicting requester otherwise
+fn check_conflicts(requirements: &BTreeMap<String, Vec<(String, String)>>) -> anyhow::Result<()> {
+    for (name, requesters) in requirements {
+        let versions: BTreeSet<&String> = requesters.iter().map(|(_, version)| version).collect();
+        if versions.len() > 1 {
+            let details = requesters
+                .iter()
+                .map(|(requester, version)| format!("{requester} requires {version}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            anyhow::bail!("Version conflict for package {name}: {details}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requirements(entries: &[(&str, &[(&str, &str)])]) -> BTreeMap<String, Vec<(String, String)>> {
+        entries
+            .iter()
+            .map(|(name, requesters)| {
+                (
+                    name.to_string(),
+                    requesters
+                        .iter()
+                        .map(|(requester, version)| (requester.to_string(), version.to_string()))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_conflicts_allows_a_single_requester() {
+        let requirements = requirements(&[("wasi:http", &[("wit.toml", "0.2.0")])]);
+        assert!(check_conflicts(&requirements).is_ok());
+    }
+
+    #[test]
+    fn check_conflicts_allows_multiple_requesters_agreeing_on_a_version() {
+        let requirements = requirements(&[(
+            "wasi:http",
+            &[("wit.toml", "0.2.0"), ("wasi:cli", "0.2.0")],
+        )]);
+        assert!(check_conflicts(&requirements).is_ok());
+    }
+
+    #[test]
+    fn check_conflicts_rejects_disagreeing_requesters() {
+        let requirements = requirements(&[(
+            "wasi:http",
+            &[("wit.toml", "0.2.0"), ("wasi:cli", "0.2.1")],
+        )]);
+        let err = check_conflicts(&requirements).expect_err("should detect the conflict");
+        let message = err.to_string();
+        assert!(message.contains("wasi:http"));
+        assert!(message.contains("wit.toml requires 0.2.0"));
+        assert!(message.contains("wasi:cli requires 0.2.1"));
+    }
+
+    #[test]
+    fn digests_match_tolerates_missing_sha256_prefix() {
+        assert!(digests_match("sha256:abc", "abc"));
+        assert!(digests_match("abc", "sha256:abc"));
+        assert!(!digests_match("sha256:abc", "sha256:def"));
+    }
 }
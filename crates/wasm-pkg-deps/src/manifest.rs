@@ -1,8 +1,15 @@
 //! Definitions and helpers for loading the `wit.toml` and `wit.lock` dependency manifest files
 use std::collections::BTreeMap;
+use std::path::Path;
 
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
 
+/// The default manifest file name
+pub const DEFAULT_MANIFEST_FILE_NAME: &str = "wit.toml";
+/// The default lock file name
+pub const DEFAULT_LOCK_FILE_NAME: &str = "wit.lock";
+
 /// The dependency configuration format. The key names are the package name you want to pull (e.g.
 /// `wasi:http`).
 ///
@@ -12,6 +19,14 @@ use serde::{Deserialize, Serialize};
 /// can override the package name in the manifest as desired
 pub type WitManifest = BTreeMap<String, ManifestEntry>;
 
+/// Reads and parses the manifest at the given path
+pub async fn load_manifest(path: impl AsRef<Path>) -> anyhow::Result<WitManifest> {
+    let raw = tokio::fs::read_to_string(path)
+        .await
+        .context("Unable to read wit.toml")?;
+    toml::from_str(&raw).context("Unable to parse wit.toml")
+}
+
 /// A single entry in the dependency manifest
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase", untagged)]
@@ -22,6 +37,23 @@ pub enum ManifestEntry {
     Config(DependencyConfig),
 }
 
+impl ManifestEntry {
+    /// Normalizes this entry into a full [`DependencyConfig`], filling in `None` for every field
+    /// left implicit by the plain version form
+    pub fn into_config(self) -> DependencyConfig {
+        match self {
+            ManifestEntry::Version(version) => DependencyConfig {
+                version,
+                registry: None,
+                protocol: None,
+                registry_subpath: None,
+                package_name: None,
+            },
+            ManifestEntry::Config(config) => config,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DependencyConfig {
@@ -44,3 +76,54 @@ pub struct DependencyConfig {
     /// then the full package name would be `my/subpath/my-wasi-http`)
     pub package_name: Option<String>,
 }
+
+/// A lockfile recording the exact version, registry, reference, and digest of every package
+/// resolved while updating a [`WitManifest`]. Keeping this alongside `wit.toml` lets subsequent
+/// `wit deps update` runs reuse a package's pulled bytes when its digest hasn't changed, instead
+/// of re-downloading every transitive dependency on every run.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WitLock {
+    /// The resolved packages, keyed by the same package name used in the manifest (e.g.
+    /// `wasi:http`)
+    #[serde(default)]
+    pub packages: BTreeMap<String, LockedPackage>,
+}
+
+impl WitLock {
+    /// Loads a lockfile from the given path, returning an empty lock if the file does not exist
+    pub async fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(raw) => toml::from_str(&raw).context("Unable to parse wit.lock"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).context("Unable to read wit.lock"),
+        }
+    }
+
+    /// Serializes and writes the lockfile to the given path
+    pub async fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let raw = toml::to_string_pretty(self).context("Unable to serialize wit.lock")?;
+        tokio::fs::write(path, raw)
+            .await
+            .context("Unable to write wit.lock")
+    }
+}
+
+/// A single resolved, locked dependency
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedPackage {
+    /// The exact version that was resolved
+    pub version: String,
+    /// The registry the package was pulled from
+    pub registry: String,
+    /// The full OCI reference that was pulled
+    pub reference: String,
+    /// The sha256 digest (in `sha256:<hex>` form) of the pulled component layer
+    pub digest: String,
+    /// The sha256 digest (in `sha256:<hex>` form) of the OCI manifest document the component was
+    /// pulled under. This is what's compared against the registry's current manifest digest to
+    /// decide whether a package needs re-pulling; it is a different digest over different content
+    /// than [`Self::digest`], so the two must never be compared against each other.
+    pub manifest_digest: String,
+}
@@ -0,0 +1,177 @@
+//! Retry/backoff wrapper around OCI client operations. Registry pulls happen in bulk when
+//! resolving transitive dependencies, so a single transient 5xx or dropped connection shouldn't
+//! abort the whole `wit deps update` run.
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+
+/// Exponential backoff (with jitter) for a bounded number of attempts
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// The total number of attempts to make, including the first. `1` disables retrying
+    pub max_attempts: u32,
+    /// The base delay used to compute each attempt's backoff
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .as_millis()
+            .saturating_mul(1u128 << attempt.min(16));
+        let jitter = rand::thread_rng().gen_range(0..=(exp / 2).max(1));
+        Duration::from_millis((exp + jitter).min(u128::from(u64::MAX)) as u64)
+    }
+}
+
+/// Whether an operation is safe to retry automatically. Pulls (manifest/blob GETs) are idempotent
+/// and may retry any transient failure (connection/timeout/5xx); pushes must only retry
+/// connection-level failures, never a 5xx (or 4xx) response, since the server may already have
+/// applied part of the write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryClass {
+    /// Retry transient failures (pulls, manifest/blob GETs)
+    Idempotent,
+    /// Only retry transient, connection-level failures (pushes)
+    Mutating,
+}
+
+/// Retries `op` according to `policy` and `class`, sleeping with exponential backoff and jitter
+/// between attempts. Returns the last error once attempts are exhausted or the error isn't
+/// retryable for `class`.
+pub async fn retry<T, F, Fut>(policy: RetryPolicy, class: RetryClass, mut op: F) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt + 1 < policy.max_attempts && is_retryable(class, &e) => {
+                tokio::time::sleep(policy.delay_for(attempt)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Decides whether `err` is worth retrying for the given [`RetryClass`]. `Idempotent` retries any
+/// transient failure; `Mutating` only retries connection-level failures, since a 5xx carries the
+/// same partial-write risk as a 4xx once the request may have reached the server.
+fn is_retryable(class: RetryClass, err: &anyhow::Error) -> bool {
+    match class {
+        RetryClass::Idempotent => is_transient(err),
+        RetryClass::Mutating => is_connection_level(err),
+    }
+}
+
+/// Whether `err` looks like a connection-level failure: the request never got a response at all
+fn is_connection_level(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| cause.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_connect() || e.is_timeout()))
+}
+
+/// Whether `err` looks like a transient failure rather than a permanent one: a connection-level
+/// failure, or a 5xx response
+fn is_transient(err: &anyhow::Error) -> bool {
+    is_connection_level(err)
+        || err.chain().any(|cause| {
+            cause
+                .downcast_ref::<reqwest::Error>()
+                .is_some_and(|e| e.status().is_some_and(|s| s.is_server_error()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_for_grows_exponentially() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+        };
+        assert!(policy.delay_for(0) >= Duration::from_millis(100));
+        assert!(policy.delay_for(1) >= Duration::from_millis(200));
+        assert!(policy.delay_for(2) >= Duration::from_millis(400));
+    }
+
+    #[test]
+    fn permanent_errors_are_not_retryable_for_either_class() {
+        let err = anyhow::anyhow!("bad credentials");
+        assert!(!is_retryable(RetryClass::Idempotent, &err));
+        assert!(!is_retryable(RetryClass::Mutating, &err));
+    }
+
+    #[tokio::test]
+    async fn connection_failures_are_retryable_for_both_classes() {
+        // Nothing listens on this port, so this fails fast with a connect error
+        let err = reqwest::get("http://127.0.0.1:1")
+            .await
+            .expect_err("connection should fail");
+        let err = anyhow::Error::new(err);
+
+        assert!(is_retryable(RetryClass::Idempotent, &err));
+        assert!(is_retryable(RetryClass::Mutating, &err));
+    }
+
+    #[tokio::test]
+    async fn server_errors_only_retry_for_idempotent() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind should succeed");
+        let addr = listener.local_addr().expect("local_addr should succeed");
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = stream
+                    .write_all(b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n");
+            }
+        });
+
+        let response = reqwest::get(format!("http://{addr}"))
+            .await
+            .expect("request should complete");
+        let err = response.error_for_status().expect_err("500 should be an error");
+        let err = anyhow::Error::new(err);
+
+        assert!(is_retryable(RetryClass::Idempotent, &err));
+        assert!(!is_retryable(RetryClass::Mutating, &err));
+    }
+
+    #[tokio::test]
+    async fn retry_stops_after_max_attempts() {
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+        };
+
+        let result: anyhow::Result<()> = retry(policy, RetryClass::Idempotent, || async {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let err = reqwest::get("http://127.0.0.1:1")
+                .await
+                .expect_err("connection should fail");
+            Err(anyhow::Error::new(err))
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+}
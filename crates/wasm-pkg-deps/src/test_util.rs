@@ -0,0 +1,322 @@
+//! An in-process fake OCI registry, for hermetically testing [`crate::DepsClient`], the resolver,
+//! and [`crate::config::RegistryConfig::get_client`] without a real registry. Speaks just enough
+//! of the OCI distribution API to serve WASM components: accepts blob and manifest `PUT`s,
+//! computes and stores their sha256 digests, and serves `GET`s by tag or digest. This follows
+//! cargo's local test-registry pattern. Only available behind the `test-util` feature.
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+
+use crate::config::{Config, RegistryConfig};
+
+#[derive(Default)]
+struct Store {
+    /// Blobs (the config and component-layer bytes), keyed by their `sha256:<hex>` digest
+    blobs: HashMap<String, Bytes>,
+    /// OCI manifest documents, keyed by their `sha256:<hex>` digest
+    manifests: HashMap<String, Bytes>,
+    /// The manifest digest a `repository:tag` reference currently resolves to
+    tags: HashMap<String, String>,
+}
+
+type SharedStore = Arc<Mutex<Store>>;
+
+/// A running fake OCI registry. Dropping this stops the background server task.
+pub struct FakeRegistry {
+    addr: SocketAddr,
+    store: SharedStore,
+    _server: tokio::task::JoinHandle<()>,
+}
+
+impl FakeRegistry {
+    /// Starts a fake registry listening on an OS-assigned local port
+    pub async fn start() -> anyhow::Result<Self> {
+        let store: SharedStore = Arc::new(Mutex::new(Store::default()));
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let app = router(store.clone());
+        let server = tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        Ok(Self {
+            addr,
+            store,
+            _server: server,
+        })
+    }
+
+    /// The `host:port` this registry is listening on, suitable for use as a
+    /// [`RegistryConfig::registry`]
+    pub fn host(&self) -> String {
+        self.addr.to_string()
+    }
+
+    /// Seeds the registry with a component's bytes under `subpath/name:version`, wrapping them in
+    /// a real OCI manifest document (with a config blob and a layer descriptor) rather than
+    /// serving the raw bytes as if they were the manifest. Returns the digest the component's
+    /// bytes were stored under (the layer digest, matching [`crate::manifest::LockedPackage::digest`]).
+    pub fn seed_component(
+        &self,
+        subpath: &str,
+        name: &str,
+        version: &str,
+        data: impl Into<Vec<u8>>,
+    ) -> String {
+        let data = data.into();
+        let layer_digest = format!("sha256:{:x}", Sha256::digest(&data));
+
+        let config_bytes = b"{}".to_vec();
+        let config_digest = format!("sha256:{:x}", Sha256::digest(&config_bytes));
+
+        let manifest = OciManifest {
+            schema_version: 2,
+            media_type: oci_wasm::WASM_MANIFEST_MEDIA_TYPE,
+            config: OciDescriptor {
+                media_type: oci_wasm::WASM_MANIFEST_CONFIG_MEDIA_TYPE,
+                digest: config_digest.clone(),
+                size: config_bytes.len() as u64,
+            },
+            layers: vec![OciDescriptor {
+                media_type: oci_wasm::WASM_LAYER_MEDIA_TYPE,
+                digest: layer_digest.clone(),
+                size: data.len() as u64,
+            }],
+        };
+        let manifest_bytes = serde_json::to_vec(&manifest).expect("manifest should serialize");
+        let manifest_digest = format!("sha256:{:x}", Sha256::digest(&manifest_bytes));
+
+        let mut store = self.store.lock().unwrap();
+        store.blobs.insert(layer_digest.clone(), Bytes::from(data));
+        store.blobs.insert(config_digest, Bytes::from(config_bytes));
+        store
+            .manifests
+            .insert(manifest_digest.clone(), Bytes::from(manifest_bytes));
+        store
+            .tags
+            .insert(format!("{subpath}/{name}:{version}"), manifest_digest);
+
+        layer_digest
+    }
+
+    /// A [`Config`] whose default namespace points at this registry over plain HTTP
+    pub fn config(&self) -> Config {
+        Config {
+            default_namespace: "test".to_string(),
+            default_config: self.registry_config(),
+            namespaces: Default::default(),
+        }
+    }
+
+    /// A [`RegistryConfig`] pointed at this registry over plain HTTP
+    pub fn registry_config(&self) -> RegistryConfig {
+        RegistryConfig {
+            registry: self.host(),
+            protocol: Some("http".to_string()),
+            registry_subpath: None,
+            auth: None,
+            retry: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OciDescriptor {
+    media_type: &'static str,
+    digest: String,
+    size: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OciManifest {
+    schema_version: u32,
+    media_type: &'static str,
+    config: OciDescriptor,
+    layers: Vec<OciDescriptor>,
+}
+
+/// The repository path and the trailing `manifests`/`blobs` segment, split out of a request's
+/// catch-all path. A single `{*rest}` route is used because axum rejects a catch-all wildcard
+/// followed by further literal segments, so the `manifests`/`blobs` split has to happen manually.
+enum Route {
+    Manifest { repo: String, reference: String },
+    Blob { repo: String, digest: String },
+}
+
+fn parse_route(rest: &str) -> Option<Route> {
+    if let Some((repo, reference)) = rest.rsplit_once("/manifests/") {
+        return Some(Route::Manifest {
+            repo: repo.to_string(),
+            reference: reference.to_string(),
+        });
+    }
+    if let Some((repo, digest)) = rest.rsplit_once("/blobs/") {
+        return Some(Route::Blob {
+            repo: repo.to_string(),
+            digest: digest.to_string(),
+        });
+    }
+    None
+}
+
+fn router(store: SharedStore) -> Router {
+    Router::new()
+        .route("/v2/{*rest}", get(handle_get).put(handle_put))
+        .with_state(store)
+}
+
+async fn handle_put(
+    State(store): State<SharedStore>,
+    Path(rest): Path<String>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Some(route) = parse_route(&rest) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let digest = format!("sha256:{:x}", Sha256::digest(&body));
+    let mut store = store.lock().unwrap();
+    match route {
+        Route::Manifest { repo, reference } => {
+            store.manifests.insert(digest.clone(), body);
+            store.tags.insert(format!("{repo}:{reference}"), digest.clone());
+        }
+        Route::Blob { .. } => {
+            store.blobs.insert(digest.clone(), body);
+        }
+    }
+    (StatusCode::CREATED, [("Docker-Content-Digest", digest)]).into_response()
+}
+
+async fn handle_get(State(store): State<SharedStore>, Path(rest): Path<String>) -> impl IntoResponse {
+    let Some(route) = parse_route(&rest) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let store = store.lock().unwrap();
+    match route {
+        Route::Manifest { repo, reference } => {
+            let digest = store
+                .tags
+                .get(&format!("{repo}:{reference}"))
+                .cloned()
+                .or_else(|| store.manifests.contains_key(&reference).then(|| reference.clone()));
+            let Some(digest) = digest else {
+                return StatusCode::NOT_FOUND.into_response();
+            };
+            match store.manifests.get(&digest).cloned() {
+                Some(data) => (
+                    StatusCode::OK,
+                    [
+                        ("Content-Type", oci_wasm::WASM_MANIFEST_MEDIA_TYPE.to_string()),
+                        ("Docker-Content-Digest", digest),
+                    ],
+                    data,
+                )
+                    .into_response(),
+                None => StatusCode::NOT_FOUND.into_response(),
+            }
+        }
+        Route::Blob { digest, .. } => match store.blobs.get(&digest).cloned() {
+            Some(data) => (StatusCode::OK, data).into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The smallest syntactically valid WebAssembly component: the 8-byte header (magic, version,
+    /// layer) with no sections at all, so it decodes to a component with no imports/exports.
+    /// Enough to drive [`crate::DepsClient::update`] end to end without needing a real built
+    /// component fixture.
+    const EMPTY_COMPONENT: &[u8] = &[0x00, 0x61, 0x73, 0x6D, 0x0d, 0x00, 0x01, 0x00];
+
+    #[tokio::test]
+    async fn seeded_component_is_served_as_a_real_manifest() {
+        let registry = FakeRegistry::start().await.expect("fake registry should start");
+        let layer_digest = registry.seed_component("my", "pkg", "0.1.0", EMPTY_COMPONENT.to_vec());
+
+        let url = format!("http://{}/v2/my/pkg/manifests/0.1.0", registry.host());
+        let response = reqwest::get(url).await.expect("request should succeed");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let manifest: serde_json::Value =
+            response.json().await.expect("manifest body should be json");
+
+        assert_eq!(manifest["schemaVersion"], 2);
+        assert_eq!(manifest["layers"][0]["digest"], layer_digest);
+
+        let layer_url = format!("http://{}/v2/my/pkg/blobs/{layer_digest}", registry.host());
+        let layer_body = reqwest::get(layer_url)
+            .await
+            .expect("request should succeed")
+            .bytes()
+            .await
+            .expect("body should read");
+        assert_eq!(layer_body.as_ref(), EMPTY_COMPONENT);
+    }
+
+    #[tokio::test]
+    async fn unknown_reference_returns_not_found() {
+        let registry = FakeRegistry::start().await.expect("fake registry should start");
+
+        let url = format!("http://{}/v2/my/pkg/manifests/9.9.9", registry.host());
+        let status = reqwest::get(url).await.expect("request should succeed").status();
+
+        assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn deps_client_update_resolves_and_reuses_cache_against_a_fake_registry() {
+        let registry = FakeRegistry::start().await.expect("fake registry should start");
+        let layer_digest = registry.seed_component("test", "wasi-http", "0.2.0", EMPTY_COMPONENT.to_vec());
+
+        let mut manifest = crate::manifest::WitManifest::new();
+        manifest.insert(
+            "wasi:http".to_string(),
+            crate::manifest::ManifestEntry::Version("0.2.0".to_string()),
+        );
+
+        let client = crate::DepsClient::new(registry.config());
+        let cache_dir = std::env::temp_dir().join(format!(
+            "wasm-pkg-deps-test-cache-{}",
+            registry.host().replace([':', '.'], "-")
+        ));
+        let mut lock = crate::manifest::WitLock::default();
+
+        client
+            .update(&manifest, &cache_dir, &mut lock)
+            .await
+            .expect("update should succeed");
+
+        let locked = lock.packages.get("wasi:http").expect("package should be locked");
+        assert_eq!(locked.version, "0.2.0");
+        assert_eq!(locked.digest, layer_digest);
+
+        let first_manifest_digest = locked.manifest_digest.clone();
+
+        // A second run against the unchanged registry should reuse the lockfile's manifest digest
+        // rather than re-pulling, and land on the exact same lock entry.
+        client
+            .update(&manifest, &cache_dir, &mut lock)
+            .await
+            .expect("second update should succeed");
+        assert_eq!(lock.packages["wasi:http"].digest, layer_digest);
+        assert_eq!(lock.packages["wasi:http"].manifest_digest, first_manifest_digest);
+
+        let _ = std::fs::remove_dir_all(&cache_dir);
+    }
+}
@@ -25,6 +25,14 @@ enum SubCommand {
     /// Manage dependencies for a project
     #[clap(subcommand)]
     Deps(DepsSubcommand),
+    /// Log in to a registry, persisting the credentials for future commands
+    Login(LoginArgs),
+    /// Remove any persisted credentials for a registry
+    Logout(LogoutArgs),
+    /// Show the identity currently persisted for a registry
+    Whoami(WhoamiArgs),
+    /// Inspect and edit the dependency config file
+    Config(ConfigArgs),
 }
 
 #[derive(Debug, Args)]
@@ -68,6 +76,28 @@ struct Common {
         value_delimiter = ','
     )]
     pub insecure: Vec<String>,
+
+    /// The total number of attempts to make against the registry, including the first, before
+    /// giving up. Pushes only retry connection-level failures regardless of this value
+    #[clap(long = "max-retries", env = "WASM_PKG_MAX_RETRIES", default_value_t = 3)]
+    pub max_retries: u32,
+
+    /// The base delay (in milliseconds) used to compute each retry's exponential backoff
+    #[clap(
+        long = "retry-base-delay-ms",
+        env = "WASM_PKG_RETRY_BASE_DELAY_MS",
+        default_value_t = 200
+    )]
+    pub retry_base_delay_ms: u64,
+}
+
+impl From<&Common> for wasm_pkg_deps::retry::RetryPolicy {
+    fn from(common: &Common) -> Self {
+        wasm_pkg_deps::retry::RetryPolicy {
+            max_attempts: common.max_retries,
+            base_delay: std::time::Duration::from_millis(common.retry_base_delay_ms),
+        }
+    }
 }
 
 #[derive(Debug, Args)]
@@ -122,6 +152,73 @@ struct DepsUpdateArgs {
     pub cache_dir: Option<PathBuf>,
 }
 
+#[derive(Debug, Args)]
+struct LoginArgs {
+    /// The registry host to log in to (e.g. ghcr.io)
+    pub registry: String,
+
+    /// The username to authenticate with. Prompted for if not provided
+    #[clap(short = 'u', long = "username")]
+    pub username: Option<String>,
+
+    /// The password to authenticate with. Prompted for if not provided
+    #[clap(short = 'p', long = "password")]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct LogoutArgs {
+    /// The registry host to log out of
+    pub registry: String,
+}
+
+#[derive(Debug, Args)]
+struct WhoamiArgs {
+    /// The registry host to look up the stored identity for
+    pub registry: String,
+}
+
+#[derive(Debug, Args)]
+struct ConfigArgs {
+    /// The config file to operate on
+    #[clap(short = 'c', long = "config")]
+    pub config: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    pub subcmd: ConfigSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigSubcommand {
+    /// Print the effective, merged configuration
+    Get,
+    /// Print the path the config file resolves to
+    Path,
+    /// List the configured namespaces and the registry each maps to
+    List,
+    /// Add or update the registry mapping for a package namespace, preserving its existing auth
+    /// and retry settings
+    SetNamespace(SetNamespaceArgs),
+}
+
+#[derive(Debug, Args)]
+struct SetNamespaceArgs {
+    /// The package namespace to map (e.g. the `wasi` in `wasi:http`)
+    pub namespace: String,
+
+    /// The registry to use for this namespace
+    #[clap(long = "registry")]
+    pub registry: String,
+
+    /// The protocol to use for this namespace. Must be "http" or "https"
+    #[clap(long = "protocol")]
+    pub protocol: Option<String>,
+
+    /// The registry subpath to use for this namespace
+    #[clap(long = "subpath")]
+    pub subpath: Option<String>,
+}
+
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> anyhow::Result<()> {
     let args = App::parse();
@@ -131,30 +228,42 @@ async fn main() -> anyhow::Result<()> {
         SubCommand::Deps(args) => match args {
             DepsSubcommand::Update(args) => handle_deps_update(args).await,
         },
+        SubCommand::Login(args) => handle_login(args).await,
+        SubCommand::Logout(args) => handle_logout(args).await,
+        SubCommand::Whoami(args) => handle_whoami(args).await,
+        SubCommand::Config(args) => handle_config(args).await,
     }
 }
 
 async fn handle_push(args: PushArgs) -> anyhow::Result<()> {
+    let policy = wasm_pkg_deps::retry::RetryPolicy::from(&args.common);
     let client = get_client(args.common);
     let (conf, layer) = WasmConfig::from_component(&args.file, args.author)
         .await
         .context("Unable to parse component")?;
-    let auth = args.auth.try_into()?;
-    client
-        .push(&args.reference, &auth, layer, conf, None)
-        .await
-        .context("Unable to push image")?;
+    let auth = resolve_auth(args.auth, args.reference.registry()).await?;
+    wasm_pkg_deps::retry::retry(policy, wasm_pkg_deps::retry::RetryClass::Mutating, || async {
+        client
+            .push(&args.reference, &auth, layer.clone(), conf.clone(), None)
+            .await
+            .context("Unable to push image")
+    })
+    .await?;
     println!("Pushed {}", args.reference);
     Ok(())
 }
 
 async fn handle_pull(args: PullArgs) -> anyhow::Result<()> {
+    let policy = wasm_pkg_deps::retry::RetryPolicy::from(&args.common);
     let client = get_client(args.common);
-    let auth = args.auth.try_into()?;
-    let data = client
-        .pull(&args.reference, &auth)
-        .await
-        .context("Unable to pull image")?;
+    let auth = resolve_auth(args.auth, args.reference.registry()).await?;
+    let data = wasm_pkg_deps::retry::retry(policy, wasm_pkg_deps::retry::RetryClass::Idempotent, || async {
+        client
+            .pull(&args.reference, &auth)
+            .await
+            .context("Unable to pull image")
+    })
+    .await?;
     let output_path = match args.output {
         Some(output_file) => output_file,
         None => PathBuf::from(format!(
@@ -181,9 +290,283 @@ async fn handle_pull(args: PullArgs) -> anyhow::Result<()> {
 }
 
 async fn handle_deps_update(args: DepsUpdateArgs) -> anyhow::Result<()> {
+    let config_path = args
+        .config
+        .unwrap_or_else(|| PathBuf::from(wasm_pkg_deps::config::DEFAULT_CONFIG_FILE_NAME));
+    let config = wasm_pkg_deps::Config::load(&config_path)
+        .await
+        .context("Unable to load dependency config")?;
+
+    let manifest_path = PathBuf::from(wasm_pkg_deps::manifest::DEFAULT_MANIFEST_FILE_NAME);
+    let manifest = wasm_pkg_deps::manifest::load_manifest(&manifest_path)
+        .await
+        .context("Unable to load wit.toml")?;
+
+    let lock_path = PathBuf::from(wasm_pkg_deps::manifest::DEFAULT_LOCK_FILE_NAME);
+    let mut lock = wasm_pkg_deps::manifest::WitLock::load(&lock_path)
+        .await
+        .context("Unable to load wit.lock")?;
+
+    let cache_dir = args
+        .cache_dir
+        .unwrap_or_else(|| PathBuf::from(".wit-cache"));
+
+    let credentials = wasm_pkg_deps::Credentials::load(credentials_path())
+        .await
+        .context("Unable to load credentials")?;
+    let client = wasm_pkg_deps::DepsClient::new(config).with_credentials(credentials);
+    client.update(&manifest, &cache_dir, &mut lock).await?;
+    lock.write(&lock_path)
+        .await
+        .context("Unable to write wit.lock")?;
+
+    println!("Resolved {} dependencies", lock.packages.len());
     Ok(())
 }
 
+async fn handle_login(args: LoginArgs) -> anyhow::Result<()> {
+    let username = match args.username {
+        Some(username) => username,
+        None => prompt("Username")?,
+    };
+    let password = match args.password {
+        Some(password) => password,
+        None => rpassword::prompt_password("Password: ").context("Unable to read password")?,
+    };
+
+    let auth = RegistryAuth::Basic(username.clone(), password.clone());
+    let reference: Reference = format!("{}/login-check:latest", args.registry)
+        .parse()
+        .context("Invalid registry host")?;
+    let mut client = oci_distribution::Client::default();
+    client
+        .auth(
+            &reference,
+            &auth,
+            oci_distribution::client::RegistryOperation::Pull,
+        )
+        .await
+        .context("Unable to verify credentials")?;
+
+    let path = credentials_path();
+    let mut credentials = wasm_pkg_deps::Credentials::load(&path)
+        .await
+        .context("Unable to load credentials")?;
+    credentials.set(
+        args.registry.clone(),
+        wasm_pkg_deps::credentials::StoredCredential { username, password },
+    );
+    credentials
+        .write(&path)
+        .await
+        .context("Unable to write credentials")?;
+
+    println!("Logged in to {}", args.registry);
+    Ok(())
+}
+
+async fn handle_logout(args: LogoutArgs) -> anyhow::Result<()> {
+    let path = credentials_path();
+    let mut credentials = wasm_pkg_deps::Credentials::load(&path)
+        .await
+        .context("Unable to load credentials")?;
+
+    if credentials.remove(&args.registry).is_some() {
+        credentials
+            .write(&path)
+            .await
+            .context("Unable to write credentials")?;
+        println!("Logged out of {}", args.registry);
+    } else {
+        println!("Not logged in to {}", args.registry);
+    }
+    Ok(())
+}
+
+async fn handle_whoami(args: WhoamiArgs) -> anyhow::Result<()> {
+    let path = credentials_path();
+    let credentials = wasm_pkg_deps::Credentials::load(&path)
+        .await
+        .context("Unable to load credentials")?;
+
+    match credentials.get(&args.registry) {
+        Some(stored) => println!("{}", stored.username),
+        None => println!("Not logged in to {}", args.registry),
+    }
+    Ok(())
+}
+
+async fn handle_config(args: ConfigArgs) -> anyhow::Result<()> {
+    let path = args
+        .config
+        .unwrap_or_else(|| PathBuf::from(wasm_pkg_deps::config::DEFAULT_CONFIG_FILE_NAME));
+
+    match args.subcmd {
+        ConfigSubcommand::Path => {
+            println!("{}", path.display());
+        }
+        ConfigSubcommand::Get => {
+            let config = wasm_pkg_deps::Config::load(&path)
+                .await
+                .context("Unable to load config")?;
+            print!(
+                "{}",
+                toml::to_string_pretty(&config).context("Unable to render config")?
+            );
+        }
+        ConfigSubcommand::List => {
+            let config = wasm_pkg_deps::Config::load(&path)
+                .await
+                .context("Unable to load config")?;
+            println!(
+                "{} (default): {}",
+                config.default_namespace, config.default_config.registry
+            );
+            for (namespace, registry_config) in &config.namespaces {
+                println!("{namespace}: {}", registry_config.registry);
+            }
+        }
+        ConfigSubcommand::SetNamespace(set_args) => {
+            let mut config = wasm_pkg_deps::Config::load(&path)
+                .await
+                .context("Unable to load config")?;
+
+            let existing = config.namespaces.get(&set_args.namespace).cloned();
+            let registry_config = merge_namespace_registry_config(&set_args, existing.as_ref());
+            config
+                .namespaces
+                .insert(set_args.namespace.clone(), registry_config);
+            config
+                .write(&path)
+                .await
+                .context("Unable to write config")?;
+            println!("Updated namespace {}", set_args.namespace);
+        }
+    }
+    Ok(())
+}
+
+/// Builds the [`RegistryConfig`](wasm_pkg_deps::config::RegistryConfig) to store for `set_args`,
+/// falling back to `existing`'s `protocol`/`registry_subpath`/`auth`/`retry` whenever the CLI args
+/// leave the corresponding field unset, so a partial `set-namespace` edit never wipes out fields it
+/// wasn't asked to change
+fn merge_namespace_registry_config(
+    set_args: &SetNamespaceArgs,
+    existing: Option<&wasm_pkg_deps::config::RegistryConfig>,
+) -> wasm_pkg_deps::config::RegistryConfig {
+    wasm_pkg_deps::config::RegistryConfig {
+        registry: set_args.registry.clone(),
+        protocol: set_args
+            .protocol
+            .clone()
+            .or_else(|| existing.and_then(|c| c.protocol.clone())),
+        registry_subpath: set_args
+            .subpath
+            .clone()
+            .or_else(|| existing.and_then(|c| c.registry_subpath.clone())),
+        auth: existing.and_then(|c| c.auth.clone()),
+        retry: existing.and_then(|c| c.retry),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_namespace_preserves_unset_fields_from_existing() {
+        let existing = wasm_pkg_deps::config::RegistryConfig {
+            registry: "old-host".to_string(),
+            protocol: Some("http".to_string()),
+            registry_subpath: Some("my/subpath".to_string()),
+            auth: None,
+            retry: None,
+        };
+        let set_args = SetNamespaceArgs {
+            namespace: "wasi".to_string(),
+            registry: "new-host".to_string(),
+            protocol: None,
+            subpath: None,
+        };
+
+        let merged = merge_namespace_registry_config(&set_args, Some(&existing));
+
+        assert_eq!(merged.registry, "new-host");
+        assert_eq!(merged.protocol.as_deref(), Some("http"));
+        assert_eq!(merged.registry_subpath.as_deref(), Some("my/subpath"));
+    }
+
+    #[test]
+    fn set_namespace_applies_explicit_overrides() {
+        let existing = wasm_pkg_deps::config::RegistryConfig {
+            registry: "old-host".to_string(),
+            protocol: Some("http".to_string()),
+            registry_subpath: Some("my/subpath".to_string()),
+            auth: None,
+            retry: None,
+        };
+        let set_args = SetNamespaceArgs {
+            namespace: "wasi".to_string(),
+            registry: "new-host".to_string(),
+            protocol: Some("https".to_string()),
+            subpath: Some("other/subpath".to_string()),
+        };
+
+        let merged = merge_namespace_registry_config(&set_args, Some(&existing));
+
+        assert_eq!(merged.protocol.as_deref(), Some("https"));
+        assert_eq!(merged.registry_subpath.as_deref(), Some("other/subpath"));
+    }
+
+    #[test]
+    fn set_namespace_with_no_existing_entry_uses_only_cli_args() {
+        let set_args = SetNamespaceArgs {
+            namespace: "wasi".to_string(),
+            registry: "new-host".to_string(),
+            protocol: None,
+            subpath: None,
+        };
+
+        let merged = merge_namespace_registry_config(&set_args, None);
+
+        assert_eq!(merged.registry, "new-host");
+        assert_eq!(merged.protocol, None);
+        assert_eq!(merged.registry_subpath, None);
+    }
+}
+
+/// Resolves the auth to use for `registry`, preferring (in order) explicit flags/env vars already
+/// captured in `auth`, then a persisted `login` credential, then anonymous
+async fn resolve_auth(auth: Auth, registry: &str) -> anyhow::Result<RegistryAuth> {
+    if auth.username.is_some() || auth.password.is_some() {
+        return auth.try_into();
+    }
+
+    let credentials = wasm_pkg_deps::Credentials::load(credentials_path())
+        .await
+        .context("Unable to load credentials")?;
+    Ok(credentials
+        .get(registry)
+        .map(|stored| RegistryAuth::Basic(stored.username.clone(), stored.password.clone()))
+        .unwrap_or(RegistryAuth::Anonymous))
+}
+
+fn credentials_path() -> PathBuf {
+    PathBuf::from(wasm_pkg_deps::credentials::DEFAULT_CREDENTIALS_FILE_NAME)
+}
+
+fn prompt(label: &str) -> anyhow::Result<String> {
+    use std::io::Write;
+
+    print!("{label}: ");
+    std::io::stdout().flush().context("Unable to write prompt")?;
+    let mut input = String::new();
+    std::io::stdin()
+        .read_line(&mut input)
+        .context("Unable to read input")?;
+    Ok(input.trim().to_string())
+}
+
 fn get_client(common: Common) -> WasmClient {
     let client = oci_distribution::Client::new(ClientConfig {
         protocol: if common.insecure.is_empty() {